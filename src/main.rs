@@ -1,37 +1,232 @@
 use clap::{Arg, Command};
-use rand::Rng;
+use rand::{RngCore, rngs::OsRng};
 use sha2::{Sha256, Digest};
 use chrono::{DateTime, Utc, FixedOffset};
 use reed_solomon::{Encoder, Buffer};
 use pqcrypto_kyber::kyber1024::*;
-use pqcrypto_dilithium::dilithium5::*;
-use rayon::prelude::*;
+use pqcrypto_dilithium::dilithium5;
+use pqcrypto_traits::kem::{PublicKey as _, SecretKey as _, Ciphertext as _, SharedSecret as _};
+use pqcrypto_traits::sign::{PublicKey as _, SecretKey as _, DetachedSignature as _};
+use hkdf::Hkdf;
 use parquet::file::writer::SerializedFileWriter;
 use parquet::schema::types::Type as ParquetType;
 use parquet::basic::{Compression, Encoding};
 use arrow::array::{ArrayBuilder, BinaryBuilder};
-use merkle_tree::{MerkleTree, Sha256Hash};
+use serde::{Serialize, Deserialize};
+use aead::{Aead, KeyInit, generic_array::GenericArray};
+use aes_gcm::Aes256Gcm;
+use chacha20poly1305::ChaCha20Poly1305;
+use argon2::{Argon2, Algorithm, Version, Params as Argon2Params};
+use argon2::password_hash::{SaltString, rand_core::OsRng as PhcOsRng};
+use pbkdf2::pbkdf2_hmac;
+use bcrypt::hash_with_salt as bcrypt_hash_with_salt;
 use std::error::Error;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::Path;
 use bincode::{serialize, deserialize};
 
-fn logistic_chaos(seed: f64, length: usize, r: f64) -> Vec<u8> {
-    (0..length).into_par_iter().map(|i| {
-        let mut x = seed + i as f64 * 0.000001;  // Slight perturbation for parallelism
-        x = r * x * (1.0 - x);
-        (x * 256.0) as u8 ^ rand::thread_rng().gen::<u8>()
-    }).collect()
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+enum EncryptionType {
+    AesGcm = 1,
+    ChaCha20Poly1305 = 2,
 }
 
-fn ratchet_key(old_key: &str, data_hash: &str) -> String {
+impl EncryptionType {
+    fn from_flag(flag: &str) -> Result<Self, Box<dyn Error>> {
+        match flag {
+            "aes-gcm" => Ok(EncryptionType::AesGcm),
+            "chacha20poly1305" => Ok(EncryptionType::ChaCha20Poly1305),
+            other => Err(format!("unknown cipher: {}", other).into()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+enum HashType {
+    Argon2,
+    Pbkdf2,
+    Bcrypt,
+}
+
+impl HashType {
+    fn from_flag(flag: &str) -> Result<Self, Box<dyn Error>> {
+        match flag {
+            "argon2" => Ok(HashType::Argon2),
+            "pbkdf2" => Ok(HashType::Pbkdf2),
+            "bcrypt" => Ok(HashType::Bcrypt),
+            other => Err(format!("unknown kdf: {}", other).into()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct KdfParams {
+    hash_type: HashType,
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl KdfParams {
+    fn generate(hash_type: HashType) -> Self {
+        let salt = SaltString::generate(&mut PhcOsRng);
+        let (memory_kib, iterations, parallelism) = match hash_type {
+            HashType::Argon2 => (19_456, 2, 1),
+            HashType::Pbkdf2 => (0, 600_000, 1),
+            HashType::Bcrypt => (0, 12, 1),
+        };
+        KdfParams { hash_type, salt: salt.as_str().to_string(), memory_kib, iterations, parallelism }
+    }
+}
+
+fn derive_root_key(password: &str, params: &KdfParams) -> Result<[u8; 32], Box<dyn Error>> {
+    let mut key = [0u8; 32];
+    match params.hash_type {
+        HashType::Argon2 => {
+            let argon2_params = Argon2Params::new(params.memory_kib, params.iterations, params.parallelism, Some(32))
+                .map_err(|e| e.to_string())?;
+            let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_params);
+            argon2.hash_password_into(password.as_bytes(), params.salt.as_bytes(), &mut key)
+                .map_err(|e| e.to_string())?;
+        }
+        HashType::Pbkdf2 => {
+            pbkdf2_hmac::<Sha256>(password.as_bytes(), params.salt.as_bytes(), params.iterations, &mut key);
+        }
+        HashType::Bcrypt => {
+            // bcrypt only accepts the first 72 bytes of a password, silently ignoring
+            // the rest, which would make two different passwords derive the same root
+            // key. Reject anything that long instead of letting that happen quietly.
+            if password.len() > 72 {
+                return Err("bcrypt passwords longer than 72 bytes are truncated by the algorithm; use a shorter password or a different --kdf".into());
+            }
+            // bcrypt::hash() mints its own random salt internally, which made this
+            // KDF non-deterministic (regen could never re-derive the same root key).
+            // Decode the stored salt to the 16 raw bytes bcrypt needs and hash with it
+            // directly so the same password + params always yields the same key.
+            let salt_str = SaltString::from_b64(&params.salt).map_err(|e| e.to_string())?;
+            let mut salt_bytes = [0u8; 16];
+            let decoded = salt_str.decode_b64(&mut salt_bytes).map_err(|e| e.to_string())?;
+            if decoded.len() != 16 {
+                return Err(format!("bcrypt requires a 16-byte salt, got {} bytes", decoded.len()).into());
+            }
+            let hashed = bcrypt_hash_with_salt(password.as_bytes(), params.iterations, salt_bytes)
+                .map_err(|e| e.to_string())?;
+            key.copy_from_slice(&Sha256::digest(hashed.to_string().as_bytes()));
+        }
+    }
+    Ok(key)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+enum KeySource {
+    Password(KdfParams),
+    Kyber(Vec<u8>),
+}
+
+fn expand_shared_secret(shared_secret: &SharedSecret) -> Result<[u8; 32], Box<dyn Error>> {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, shared_secret.as_bytes())
+        .expand(b"sigil-kyber", &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+#[derive(Serialize, Deserialize)]
+struct Blueprint {
+    chunks: Vec<Vec<u8>>,
+    key: String,
+    orig_len: usize,
+    ciphertext_len: usize,
+    fib_residual: String,
+    fib_word_size: usize,
+    nonce: [u8; 12],
+    cipher: EncryptionType,
+    key_source: KeySource,
+    chunk_hashes: Vec<[u8; 32]>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignedBlueprint {
+    blueprint: Blueprint,
+    signature: Vec<u8>,
+    public_key: Vec<u8>,
+}
+
+// What actually gets signed: every field *except* `chunks`. The chunk bytes are
+// the one thing regen is explicitly designed to repair (via RS parity, verified
+// against chunk_hashes), so they can't be part of the signed payload — otherwise
+// any on-disk corruption that RS could have fixed invalidates the signature
+// before repair ever gets a chance to run. chunk_hashes still binds the
+// signature to the exact chunk set that was originally produced.
+#[derive(Serialize)]
+struct BlueprintCommitment<'a> {
+    key: &'a str,
+    orig_len: usize,
+    ciphertext_len: usize,
+    fib_residual: &'a str,
+    fib_word_size: usize,
+    nonce: [u8; 12],
+    cipher: EncryptionType,
+    key_source: &'a KeySource,
+    chunk_hashes: &'a [[u8; 32]],
+}
+
+fn blueprint_commitment(blueprint: &Blueprint) -> Result<Vec<u8>, Box<dyn Error>> {
+    let commitment = BlueprintCommitment {
+        key: &blueprint.key,
+        orig_len: blueprint.orig_len,
+        ciphertext_len: blueprint.ciphertext_len,
+        fib_residual: &blueprint.fib_residual,
+        fib_word_size: blueprint.fib_word_size,
+        nonce: blueprint.nonce,
+        cipher: blueprint.cipher,
+        key_source: &blueprint.key_source,
+        chunk_hashes: &blueprint.chunk_hashes,
+    };
+    Ok(serialize(&commitment)?)
+}
+
+fn ratchet_key(old_key: &[u8], data_hash: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(old_key);
     hasher.update(data_hash);
     format!("{:x}", hasher.finalize())
 }
 
+fn encrypt_payload(cipher: EncryptionType, key: &[u8; 32], plaintext: &[u8]) -> Result<(Vec<u8>, [u8; 12]), Box<dyn Error>> {
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = GenericArray::from_slice(&nonce_bytes);
+    let ciphertext = match cipher {
+        EncryptionType::AesGcm => {
+            let aead = Aes256Gcm::new(GenericArray::from_slice(key));
+            aead.encrypt(nonce, plaintext).map_err(|_| "AES-GCM encryption failed")?
+        }
+        EncryptionType::ChaCha20Poly1305 => {
+            let aead = ChaCha20Poly1305::new(GenericArray::from_slice(key));
+            aead.encrypt(nonce, plaintext).map_err(|_| "ChaCha20-Poly1305 encryption failed")?
+        }
+    };
+    Ok((ciphertext, nonce_bytes))
+}
+
+fn decrypt_payload(cipher: EncryptionType, key: &[u8; 32], ciphertext: &[u8], nonce_bytes: &[u8; 12]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let nonce = GenericArray::from_slice(nonce_bytes);
+    let plaintext = match cipher {
+        EncryptionType::AesGcm => {
+            let aead = Aes256Gcm::new(GenericArray::from_slice(key));
+            aead.decrypt(nonce, ciphertext).map_err(|_| "AES-GCM tag verification failed")?
+        }
+        EncryptionType::ChaCha20Poly1305 => {
+            let aead = ChaCha20Poly1305::new(GenericArray::from_slice(key));
+            aead.decrypt(nonce, ciphertext).map_err(|_| "ChaCha20-Poly1305 tag verification failed")?
+        }
+    };
+    Ok(plaintext)
+}
+
 fn check_access(time_restriction: Option<DateTime<FixedOffset>>, place: Option<&str>, manner: Option<&str>) -> Result<(), Box<dyn Error>> {
     if let Some(tr) = time_restriction {
         if Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap()) > tr {
@@ -51,37 +246,108 @@ fn check_access(time_restriction: Option<DateTime<FixedOffset>>, place: Option<&
     Ok(())
 }
 
-fn fib_sequence(up_to: u64) -> Vec<u64> {
-    let mut fibs = vec![0, 1];
-    while fibs.last().cloned().unwrap_or(0) <= up_to {
+const FIB_WORD_SIZE: usize = 4;
+
+fn max_word_value(word_size: usize) -> u64 {
+    if word_size >= 8 { u64::MAX } else { (1u64 << (word_size * 8)) - 1 }
+}
+
+// Fibonacci numbers from F_2 = 1 upward, bounded by `bound` rather than by the value
+// being encoded, so decoding a word never has to reconstruct the ladder up to u64::MAX.
+fn fib_sequence_upto(bound: u64) -> Vec<u64> {
+    let mut fibs = vec![1u64, 2u64];
+    loop {
         let next = fibs[fibs.len() - 1] + fibs[fibs.len() - 2];
+        if next > bound {
+            break;
+        }
         fibs.push(next);
     }
-    fibs.pop();
     fibs
 }
 
-fn zeckendorf(num: u64) -> String {
-    if num == 0 {
-        return "0".to_string();
-    }
-    let fibs = fib_sequence(num);
-    let mut code = String::new();
-    let mut remaining = num;
-    for &f in fibs.iter().rev() {
+// Zeckendorf's theorem guarantees the greedy decomposition never selects two
+// consecutive Fibonacci numbers. We encode `value + 1` (so every word is >= 1,
+// since the code has no representation for 0) with bits ordered ascending from
+// F_2 upward, trimmed to the highest Fibonacci number actually used. That highest
+// bit is, by construction, always the one the greedy pass selected first, so it
+// is always 1 — meaning the word's last representation bit is always 1, and
+// appending a final '1' terminator always produces exactly one "11" boundary.
+fn zeckendorf_word(value: u64, fibs: &[u64]) -> String {
+    let mut remaining = value + 1;
+    let mut flags = vec![false; fibs.len()];
+    for (i, &f) in fibs.iter().enumerate().rev() {
         if f <= remaining {
-            code.push('1');
+            flags[i] = true;
             remaining -= f;
-        } else {
-            code.push('0');
         }
     }
-    code.trim_start_matches('0').to_string()
+    let highest = flags.iter().rposition(|&set| set).expect("value + 1 >= 1 always has a representation");
+    let mut code = String::with_capacity(highest + 2);
+    for &set in &flags[..=highest] {
+        code.push(if set { '1' } else { '0' });
+    }
+    code.push('1');
+    code
+}
+
+fn decode_zeckendorf_word(bits: &[u8], fibs: &[u64]) -> Result<u64, Box<dyn Error>> {
+    let mut value_plus_one = 0u64;
+    for (i, &b) in bits.iter().enumerate() {
+        if b == b'1' {
+            value_plus_one += *fibs.get(i).ok_or("malformed residual")?;
+        }
+    }
+    Ok(value_plus_one - 1)
+}
+
+fn zeckendorf_encode_stream(data: &[u8], word_size: usize) -> String {
+    let fibs = fib_sequence_upto(max_word_value(word_size).saturating_add(1));
+    let mut bitstream = String::new();
+    for chunk in data.chunks(word_size) {
+        let value = chunk.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+        bitstream.push_str(&zeckendorf_word(value, &fibs));
+    }
+    bitstream
+}
+
+fn zeckendorf_decode_stream(bitstream: &str, word_size: usize, byte_len: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+    let fibs = fib_sequence_upto(max_word_value(word_size).saturating_add(1));
+    let mut out = Vec::with_capacity(byte_len);
+    let mut word_bits: Vec<u8> = Vec::new();
+    let mut chars = bitstream.bytes().peekable();
+    while out.len() < byte_len {
+        let c = match chars.next() {
+            Some(c) => c,
+            None => break,
+        };
+        word_bits.push(c);
+        if c == b'1' && chars.peek() == Some(&b'1') {
+            chars.next();
+            // A word whose "11" boundary comes later than any valid word could reach
+            // (more bits than fibs has entries) indexes past the end of fibs; surface
+            // that as a decode error instead of panicking on out-of-bounds access. A
+            // stream that ends with no boundary at all is not an error here: the loop
+            // stops and returns fewer than `byte_len` bytes.
+            let value = decode_zeckendorf_word(&word_bits, &fibs)?;
+            let remaining = byte_len - out.len();
+            let n = remaining.min(word_size);
+            out.extend_from_slice(&value.to_be_bytes()[8 - n..]);
+            word_bits.clear();
+        }
+    }
+    Ok(out)
 }
 
-fn decode_zeckendorf(code: &str) -> u64 {
-    let fibs = fib_sequence(u64::MAX);
-    code.chars().rev().enumerate().fold(0u64, |num, (i, bit)| if bit == '1' { num + fibs[i + 2] } else { num })
+const PARITY_COUNT: usize = 2;
+
+// Plain per-chunk SHA-256 hashes, stored alongside the chunks so corrupted ones
+// can be localized for RS repair. No Merkle tree: a tree only pays for itself
+// with inclusion proofs against a single root, and nothing here ever needs to
+// prove membership of one chunk without the rest — every chunk is hashed and
+// checked anyway, so a flat hash list costs the same and is honest about it.
+fn hash_chunks(chunks: &[Vec<u8>]) -> Vec<[u8; 32]> {
+    chunks.iter().map(|c| Sha256::digest(c).into()).collect()
 }
 
 fn add_rs_parity(data_chunks: &[Vec<u8>], parity_count: usize) -> Vec<Vec<u8>> {
@@ -102,63 +368,149 @@ fn regenerate_with_rs(chunks_with_parity: &mut [Vec<u8>], missing_indices: &[usi
     Ok(())
 }
 
-fn sigil_transform(data: &[u8], seed_key: &str, time_restriction: Option<DateTime<FixedOffset>>, place: Option<&str>, manner: Option<&str>) -> Result<(Vec<Vec<u8>>, String, usize, String), Box<dyn Error>> {
+fn sigil_transform(data: &[u8], root_key: &[u8; 32], cipher: EncryptionType, key_source: KeySource, time_restriction: Option<DateTime<FixedOffset>>, place: Option<&str>, manner: Option<&str>) -> Result<Blueprint, Box<dyn Error>> {
     check_access(time_restriction, place, manner)?;
-    let seed = 0.314159;
-    let chaos_seq = logistic_chaos(seed, data.len(), 3.99);
-    let encrypted = data.iter().zip(chaos_seq.iter()).map(|(&b, &c)| b ^ c).collect::<Vec<u8>>();
+    let (encrypted, nonce) = encrypt_payload(cipher, root_key, data)?;
     let data_hash = {
         let mut hasher = Sha256::new();
         hasher.update(&encrypted);
         format!("{:x}", hasher.finalize())
     };
-    let new_key = ratchet_key(seed_key, &data_hash);
+    let new_key = ratchet_key(root_key, &data_hash);
     let chunk_size = 4;
-    let mut chunks = (0..encrypted.len()).step_by(chunk_size).map(|i| {
+    let chunks = (0..encrypted.len()).step_by(chunk_size).map(|i| {
         let mut chunk = encrypted[i..std::cmp::min(i + chunk_size, encrypted.len())].to_vec();
         chunk.resize(chunk_size, 0);
         chunk
     }).collect::<Vec<_>>();
-    let chunks_with_parity = add_rs_parity(&chunks, 2);
-    let fib_residual = zeckendorf(encrypted.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64));
-    Ok((chunks_with_parity, new_key, data.len(), fib_residual))
+    let chunks_with_parity = add_rs_parity(&chunks, PARITY_COUNT);
+    let chunk_hashes = hash_chunks(&chunks_with_parity);
+    let fib_residual = zeckendorf_encode_stream(&encrypted, FIB_WORD_SIZE);
+    Ok(Blueprint {
+        chunks: chunks_with_parity,
+        key: new_key,
+        orig_len: data.len(),
+        ciphertext_len: encrypted.len(),
+        fib_residual,
+        fib_word_size: FIB_WORD_SIZE,
+        nonce,
+        cipher,
+        key_source,
+        chunk_hashes,
+    })
 }
 
-fn sigil_regenerate(chunks_with_parity: &mut [Vec<u8>], seed_key: &str, missing_indices: &[usize], original_length: usize, fib_residual: &str) -> Result<Vec<u8>, Box<dyn Error>> {
-    regenerate_with_rs(chunks_with_parity, missing_indices)?;
+fn sigil_regenerate(blueprint: &mut Blueprint, root_key: &[u8; 32]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let missing_indices: Vec<usize> = blueprint.chunks.iter().zip(blueprint.chunk_hashes.iter())
+        .enumerate()
+        .filter_map(|(i, (chunk, stored_hash))| {
+            let hash: [u8; 32] = Sha256::digest(chunk).into();
+            if &hash != stored_hash { Some(i) } else { None }
+        })
+        .collect();
+    if missing_indices.len() > PARITY_COUNT {
+        return Err("unrecoverable: more chunks corrupted than parity can repair".into());
+    }
+    regenerate_with_rs(&mut blueprint.chunks, &missing_indices)?;
     let mut encrypted = Vec::new();
-    for chunk in &chunks_with_parity[..chunks_with_parity.len() - 2] {
+    for chunk in &blueprint.chunks[..blueprint.chunks.len() - PARITY_COUNT] {
         encrypted.extend_from_slice(chunk);
     }
-    let fib_check = decode_zeckendorf(fib_residual);
-    let encrypted_check = encrypted.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
-    if fib_check != encrypted_check {
+    encrypted.truncate(blueprint.ciphertext_len);
+    let fib_check = zeckendorf_decode_stream(&blueprint.fib_residual, blueprint.fib_word_size, blueprint.ciphertext_len)?;
+    if fib_check != encrypted {
         return Err("Fibonacci mismatch".into());
     }
-    let seed = 0.314159;
-    let chaos_seq = logistic_chaos(seed, encrypted.len(), 3.99);
-    let data = encrypted.iter().zip(chaos_seq.iter()).map(|(&b, &c)| b ^ c).collect::<Vec<u8>>();
-    Ok(data[..original_length].to_vec())
+    let data = decrypt_payload(blueprint.cipher, root_key, &encrypted, &blueprint.nonce)?;
+    Ok(data[..blueprint.orig_len].to_vec())
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let matches = Command::new("Sigil")
-        .subcommand(Command::new("create").arg(Arg::new("input").required(true)).arg(Arg::new("output").required(true)))
-        .subcommand(Command::new("regen").arg(Arg::new("input").required(true)).arg(Arg::new("output").required(true)))
+        .subcommand(
+            Command::new("create")
+                .arg(Arg::new("input").required(true))
+                .arg(Arg::new("output").required(true))
+                .arg(Arg::new("cipher").long("cipher").default_value("aes-gcm"))
+                .arg(Arg::new("password").long("password"))
+                .arg(Arg::new("kdf").long("kdf").default_value("argon2"))
+                .arg(Arg::new("recipient").long("recipient"))
+                .arg(Arg::new("signing-key").long("signing-key").required(true))
+                .arg(Arg::new("signing-pubkey").long("signing-pubkey").required(true)),
+        )
+        .subcommand(
+            Command::new("regen")
+                .arg(Arg::new("input").required(true))
+                .arg(Arg::new("output").required(true))
+                .arg(Arg::new("password").long("password"))
+                .arg(Arg::new("secret-key").long("secret-key"))
+                .arg(Arg::new("trusted-pubkey").long("trusted-pubkey").required(true)),
+        )
+        .subcommand(
+            Command::new("keygen")
+                .arg(Arg::new("pubkey-out").required(true))
+                .arg(Arg::new("secretkey-out").required(true))
+                .arg(Arg::new("algo").long("algo").default_value("kyber")),
+        )
+        .subcommand(
+            Command::new("sign")
+                .arg(Arg::new("message").required(true))
+                .arg(Arg::new("secret-key").required(true))
+                .arg(Arg::new("signature-out").required(true)),
+        )
+        .subcommand(
+            Command::new("verify")
+                .arg(Arg::new("message").required(true))
+                .arg(Arg::new("public-key").required(true))
+                .arg(Arg::new("signature").required(true)),
+        )
         .get_matches();
 
     match matches.subcommand() {
         Some(("create", sub_matches)) => {
             let input_path = sub_matches.get_one::<String>("input").unwrap();
             let output_path = sub_matches.get_one::<String>("output").unwrap();
+            let cipher = EncryptionType::from_flag(sub_matches.get_one::<String>("cipher").unwrap())?;
+            let (root_key, key_source) = if let Some(recipient_path) = sub_matches.get_one::<String>("recipient") {
+                let mut pk_bytes = Vec::new();
+                File::open(recipient_path)?.read_to_end(&mut pk_bytes)?;
+                let public_key = PublicKey::from_bytes(&pk_bytes)?;
+                let (shared_secret, ciphertext) = encapsulate(&public_key);
+                let root_key = expand_shared_secret(&shared_secret)?;
+                (root_key, KeySource::Kyber(ciphertext.as_bytes().to_vec()))
+            } else {
+                let password = sub_matches.get_one::<String>("password").ok_or("either --password or --recipient is required")?;
+                let hash_type = HashType::from_flag(sub_matches.get_one::<String>("kdf").unwrap())?;
+                let kdf_params = KdfParams::generate(hash_type);
+                let root_key = derive_root_key(password, &kdf_params)?;
+                (root_key, KeySource::Password(kdf_params))
+            };
             let mut file = File::open(input_path)?;
             let mut data = Vec::new();
             file.read_to_end(&mut data)?;
             let time_restriction = Some(DateTime::parse_from_rfc3339("2025-12-31T23:59:59+00:00").expect("Invalid time"));
-            let (transformed, new_key, orig_len, fib_residual) = sigil_transform(&data, "initial_seed", time_restriction, Some("allowed_location"), Some("read_only"))?;
-            let blueprint = serialize(& (transformed, new_key, orig_len, fib_residual))?;
+            let blueprint = sigil_transform(&data, &root_key, cipher, key_source, time_restriction, Some("allowed_location"), Some("read_only"))?;
+            let commitment_bytes = blueprint_commitment(&blueprint)?;
+            // Sign with the creator's persistent Dilithium5 identity (produced once via
+            // `keygen --algo dilithium`), not a fresh keypair per blueprint — a freshly
+            // minted keypair would let anyone self-certify a blueprint as their own.
+            let signing_key_path = sub_matches.get_one::<String>("signing-key").unwrap();
+            let signing_pubkey_path = sub_matches.get_one::<String>("signing-pubkey").unwrap();
+            let mut signing_secret_key_bytes = Vec::new();
+            File::open(signing_key_path)?.read_to_end(&mut signing_secret_key_bytes)?;
+            let mut signing_public_key_bytes = Vec::new();
+            File::open(signing_pubkey_path)?.read_to_end(&mut signing_public_key_bytes)?;
+            let signing_secret_key = dilithium5::SecretKey::from_bytes(&signing_secret_key_bytes)?;
+            let signing_public_key = dilithium5::PublicKey::from_bytes(&signing_public_key_bytes)?;
+            let signature = dilithium5::detached_sign(&commitment_bytes, &signing_secret_key);
+            let signed_blueprint = SignedBlueprint {
+                blueprint,
+                signature: signature.as_bytes().to_vec(),
+                public_key: signing_public_key.as_bytes().to_vec(),
+            };
+            let serialized = serialize(&signed_blueprint)?;
             let mut out_file = File::create(output_path)?;
-            out_file.write_all(&blueprint)?;
+            out_file.write_all(&serialized)?;
             let parquet_path = "output.parquet";
             let schema = ParquetType::group_type_builder("schema")
                 .with_fields(vec![
@@ -182,12 +534,94 @@ fn main() -> Result<(), Box<dyn Error>> {
             let mut file = File::open(input_path)?;
             let mut blueprint_data = Vec::new();
             file.read_to_end(&mut blueprint_data)?;
-            let (mut transformed, new_key, orig_len, fib_residual) = deserialize(&blueprint_data)?;
-            let regenerated = sigil_regenerate(&mut transformed, "initial_seed", &[0, 1], orig_len, &fib_residual)?;
+            let signed_blueprint: SignedBlueprint = deserialize(&blueprint_data)?;
+            let commitment_bytes = blueprint_commitment(&signed_blueprint.blueprint)?;
+            // Verify against a caller-pinned trusted pubkey, not the one embedded in the
+            // file: trusting the embedded key would let anyone re-sign a tampered
+            // blueprint with their own keypair and swap in their own pubkey alongside it,
+            // which proves the file is internally consistent but says nothing about who
+            // actually created it. The embedded public_key is left in the wire format for
+            // inspection only; it is never itself a trust anchor.
+            let trusted_pubkey_path = sub_matches.get_one::<String>("trusted-pubkey").unwrap();
+            let mut trusted_pubkey_bytes = Vec::new();
+            File::open(trusted_pubkey_path)?.read_to_end(&mut trusted_pubkey_bytes)?;
+            let trusted_public_key = dilithium5::PublicKey::from_bytes(&trusted_pubkey_bytes)?;
+            let signature = dilithium5::DetachedSignature::from_bytes(&signed_blueprint.signature)?;
+            dilithium5::verify_detached_signature(&signature, &commitment_bytes, &trusted_public_key)
+                .map_err(|_| "blueprint signature verification failed")?;
+            let mut blueprint = signed_blueprint.blueprint;
+            let root_key = match &blueprint.key_source {
+                KeySource::Kyber(ct_bytes) => {
+                    let secret_key_path = sub_matches.get_one::<String>("secret-key").ok_or("this blueprint requires --secret-key")?;
+                    let mut sk_bytes = Vec::new();
+                    File::open(secret_key_path)?.read_to_end(&mut sk_bytes)?;
+                    let secret_key = SecretKey::from_bytes(&sk_bytes)?;
+                    let ciphertext = Ciphertext::from_bytes(ct_bytes)?;
+                    let shared_secret = decapsulate(&ciphertext, &secret_key);
+                    expand_shared_secret(&shared_secret)?
+                }
+                KeySource::Password(kdf_params) => {
+                    let password = sub_matches.get_one::<String>("password").ok_or("this blueprint requires --password")?;
+                    derive_root_key(password, kdf_params)?
+                }
+            };
+            let regenerated = sigil_regenerate(&mut blueprint, &root_key)?;
             let mut out_file = File::create(output_path)?;
             out_file.write_all(&regenerated)?;
             println!("Regenerated file at {}", output_path);
         }
+        Some(("keygen", sub_matches)) => {
+            let pubkey_path = sub_matches.get_one::<String>("pubkey-out").unwrap();
+            let secretkey_path = sub_matches.get_one::<String>("secretkey-out").unwrap();
+            match sub_matches.get_one::<String>("algo").unwrap().as_str() {
+                "kyber" => {
+                    let (public_key, secret_key) = keypair();
+                    File::create(pubkey_path)?.write_all(public_key.as_bytes())?;
+                    File::create(secretkey_path)?.write_all(secret_key.as_bytes())?;
+                    println!("Kyber1024 keypair written to {} and {}", pubkey_path, secretkey_path);
+                }
+                "dilithium" => {
+                    let (public_key, secret_key) = dilithium5::keypair();
+                    File::create(pubkey_path)?.write_all(public_key.as_bytes())?;
+                    File::create(secretkey_path)?.write_all(secret_key.as_bytes())?;
+                    println!("Dilithium5 keypair written to {} and {}", pubkey_path, secretkey_path);
+                }
+                other => return Err(format!("unknown algo: {}", other).into()),
+            }
+        }
+        Some(("sign", sub_matches)) => {
+            let message_path = sub_matches.get_one::<String>("message").unwrap();
+            let secretkey_path = sub_matches.get_one::<String>("secret-key").unwrap();
+            let signature_path = sub_matches.get_one::<String>("signature-out").unwrap();
+            let mut message = Vec::new();
+            File::open(message_path)?.read_to_end(&mut message)?;
+            let mut sk_bytes = Vec::new();
+            File::open(secretkey_path)?.read_to_end(&mut sk_bytes)?;
+            let secret_key = dilithium5::SecretKey::from_bytes(&sk_bytes)?;
+            let signature = dilithium5::detached_sign(&message, &secret_key);
+            File::create(signature_path)?.write_all(signature.as_bytes())?;
+            println!("Signature written to {}", signature_path);
+        }
+        Some(("verify", sub_matches)) => {
+            let message_path = sub_matches.get_one::<String>("message").unwrap();
+            let pubkey_path = sub_matches.get_one::<String>("public-key").unwrap();
+            let signature_path = sub_matches.get_one::<String>("signature").unwrap();
+            let mut message = Vec::new();
+            File::open(message_path)?.read_to_end(&mut message)?;
+            let mut pk_bytes = Vec::new();
+            File::open(pubkey_path)?.read_to_end(&mut pk_bytes)?;
+            let mut sig_bytes = Vec::new();
+            File::open(signature_path)?.read_to_end(&mut sig_bytes)?;
+            let public_key = dilithium5::PublicKey::from_bytes(&pk_bytes)?;
+            let signature = dilithium5::DetachedSignature::from_bytes(&sig_bytes)?;
+            match dilithium5::verify_detached_signature(&signature, &message, &public_key) {
+                Ok(()) => println!("Signature valid"),
+                Err(_) => {
+                    eprintln!("Signature invalid");
+                    std::process::exit(1);
+                }
+            }
+        }
         _ => println!("Invalid command"),
     }
     Ok(())
@@ -198,15 +632,63 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_zeckendorf() {
-        assert_eq!(zeckendorf(13), "1001");
+    fn test_zeckendorf_stream_roundtrip() {
+        let cases: Vec<&[u8]> = vec![
+            b"test",
+            &[1],
+            &[0, 0, 0, 2],
+            &[0, 0, 0, 0, 0, 0, 0, 0],
+            b"a much longer payload than eight bytes, to exercise multiple Zeckendorf words",
+        ];
+        for data in cases {
+            let encoded = zeckendorf_encode_stream(data, FIB_WORD_SIZE);
+            let decoded = zeckendorf_decode_stream(&encoded, FIB_WORD_SIZE, data.len()).unwrap();
+            assert_eq!(decoded, data.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_zeckendorf_decode_rejects_malformed_residual() {
+        // "10" repeated well past the length any real word could reach, then a "11"
+        // terminator: the data portion indexes past the end of the bounded fibs
+        // ladder, which must surface as an error rather than panic.
+        let malformed = format!("{}11", "10".repeat(100));
+        assert!(zeckendorf_decode_stream(&malformed, FIB_WORD_SIZE, 4).is_err());
     }
 
     #[test]
     fn test_regen() {
         let data = b"test";
-        let (mut transformed, _, orig_len, fib_residual) = sigil_transform(data, "test_key", None, None, None).unwrap();
-        let regenerated = sigil_regenerate(&mut transformed, "test_key", &[0], orig_len, &fib_residual).unwrap();
+        let kdf_params = KdfParams::generate(HashType::Argon2);
+        let root_key = derive_root_key("test_password", &kdf_params).unwrap();
+        let mut blueprint = sigil_transform(data, &root_key, EncryptionType::AesGcm, KeySource::Password(kdf_params), None, None, None).unwrap();
+        let regenerated = sigil_regenerate(&mut blueprint, &root_key).unwrap();
+        assert_eq!(regenerated, data.to_vec());
+    }
+
+    #[test]
+    fn test_regen_recovers_from_chunk_corruption() {
+        let data = b"test";
+        let kdf_params = KdfParams::generate(HashType::Argon2);
+        let root_key = derive_root_key("test_password", &kdf_params).unwrap();
+        let mut blueprint = sigil_transform(data, &root_key, EncryptionType::AesGcm, KeySource::Password(kdf_params), None, None, None).unwrap();
+        blueprint.chunks[0][0] ^= 0xFF;
+        let regenerated = sigil_regenerate(&mut blueprint, &root_key).unwrap();
+        assert_eq!(regenerated, data.to_vec());
+    }
+
+    #[test]
+    fn test_regen_bcrypt_kdf() {
+        let data = b"test";
+        let kdf_params = KdfParams::generate(HashType::Bcrypt);
+        let root_key = derive_root_key("test_password", &kdf_params).unwrap();
+        // Re-derive from the same stored params, independently of `root_key` above,
+        // the way regen would after loading a blueprint from disk — this is what
+        // actually exercises the determinism fix rather than just the one call site.
+        let rederived_key = derive_root_key("test_password", &kdf_params).unwrap();
+        assert_eq!(root_key, rederived_key);
+        let mut blueprint = sigil_transform(data, &root_key, EncryptionType::AesGcm, KeySource::Password(kdf_params), None, None, None).unwrap();
+        let regenerated = sigil_regenerate(&mut blueprint, &rederived_key).unwrap();
         assert_eq!(regenerated, data.to_vec());
     }
 }